@@ -0,0 +1,141 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicIsize, Ordering};
+
+use crate::cpu::this_cpu_id;
+use crate::trap::disable_local;
+use crate::trap::DisabledLocalIrqGuard;
+
+const NOT_OWNED: isize = -1;
+
+/// A spin lock that may be acquired more than once by the same CPU without
+/// deadlocking.
+///
+/// `SpinLock` deadlocks if the same CPU tries to acquire it while already
+/// holding it, which makes it unusable for recursive kernel paths (e.g. a
+/// logger or allocator re-entered from inside a locked region).
+/// `ReentrantSpinLock` tracks the owning CPU and a recursion count instead:
+/// re-entrant acquisitions from the owning CPU just bump the count.
+///
+/// Because this runs with local interrupts disabled and no preemption, the
+/// owning CPU's id is a sufficient owner identity: no other task can run on
+/// that CPU while it holds the lock, so a matching CPU id can only mean the
+/// current execution context is re-entering the lock itself.
+pub struct ReentrantSpinLock<T> {
+    val: UnsafeCell<T>,
+    /// The id of the CPU currently holding the lock, or `NOT_OWNED`.
+    owner_cpu: AtomicIsize,
+    /// The recursion depth of the current owner.
+    depth: UnsafeCell<usize>,
+}
+
+impl<T> ReentrantSpinLock<T> {
+    /// Creates a new reentrant spin lock.
+    pub const fn new(val: T) -> Self {
+        Self {
+            val: UnsafeCell::new(val),
+            owner_cpu: AtomicIsize::new(NOT_OWNED),
+            depth: UnsafeCell::new(0),
+        }
+    }
+
+    /// Acquires the lock, blocking the caller if it is held by another CPU.
+    ///
+    /// If the calling CPU already holds the lock, this returns immediately
+    /// with a guard that only increments the recursion count, and does not
+    /// disable interrupts again.
+    pub fn lock(&self) -> ReentrantSpinLockGuard<T> {
+        let cpu_id = this_cpu_id() as isize;
+
+        if self.owner_cpu.load(Ordering::Relaxed) == cpu_id {
+            // SAFETY: interrupts are disabled for as long as this CPU owns
+            // the lock, so no one else can race with this update.
+            unsafe { *self.depth.get() += 1 };
+            return ReentrantSpinLockGuard {
+                lock: self,
+                irq_guard: None,
+            };
+        }
+
+        // FIXME: add disable_preemption
+        let irq_guard = disable_local();
+        while self
+            .owner_cpu
+            .compare_exchange(NOT_OWNED, cpu_id, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        // SAFETY: we just became the owner, so no one else touches `depth`.
+        unsafe { *self.depth.get() = 1 };
+        ReentrantSpinLockGuard {
+            lock: self,
+            irq_guard: Some(irq_guard),
+        }
+    }
+
+    /// Accesses the protected value via a closure, acquiring the lock for
+    /// the duration of the call. Modeled on `std::sync::ReentrantMutex`'s
+    /// `with` API, since a `&mut T` cannot be handed out safely across
+    /// reentrant acquisitions.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.lock())
+    }
+
+    fn release_lock(&self) {
+        // SAFETY: only the owning CPU calls this, with interrupts disabled.
+        let depth = unsafe { &mut *self.depth.get() };
+        *depth -= 1;
+        if *depth == 0 {
+            self.owner_cpu.store(NOT_OWNED, Ordering::Release);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ReentrantSpinLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.lock(), f)
+    }
+}
+
+// Safety. Only the owning CPU is permitted to access the inner data.
+unsafe impl<T: Send> Send for ReentrantSpinLock<T> {}
+unsafe impl<T: Send> Sync for ReentrantSpinLock<T> {}
+
+/// The guard of a reentrant spin lock.
+///
+/// Unlike `SpinLockGuard`, this only exposes `Deref`: handing out a `&mut T`
+/// would be unsound, since an outer, still-live borrow could alias with one
+/// taken by a reentrant acquisition nested inside it.
+pub struct ReentrantSpinLockGuard<'a, T> {
+    lock: &'a ReentrantSpinLock<T>,
+    /// `None` for a re-entrant acquisition, which must not disable (or later
+    /// re-enable) interrupts a second time.
+    irq_guard: Option<DisabledLocalIrqGuard>,
+}
+
+impl<'a, T> Deref for ReentrantSpinLockGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.val.get() }
+    }
+}
+
+impl<'a, T> Drop for ReentrantSpinLockGuard<'a, T> {
+    fn drop(&mut self) {
+        self.lock.release_lock();
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for ReentrantSpinLockGuard<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, T> !Send for ReentrantSpinLockGuard<'a, T> {}
+
+// Safety. ReentrantSpinLockGuard can be shared between tasks/threads in same CPU.
+unsafe impl<T: Sync> Sync for ReentrantSpinLockGuard<'_, T> {}
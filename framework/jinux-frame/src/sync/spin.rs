@@ -1,15 +1,27 @@
 use core::cell::UnsafeCell;
 use core::fmt;
 use core::ops::{Deref, DerefMut};
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::trap::disable_local;
 use crate::trap::DisabledLocalIrqGuard;
 
+/// The maximum number of `spin_loop` iterations between re-reads of
+/// `now_serving`, used to cap the exponential backoff below.
+const MAX_BACKOFF_SPINS: usize = 1 << 10;
+
 /// A spin lock.
+///
+/// Acquisition is a ticket lock: each acquirer atomically takes the next
+/// ticket, then busy-waits until `now_serving` reaches it. This makes
+/// acquisition FIFO-fair and, since waiters only read (rather than
+/// read-modify-write) a cache line while spinning, avoids the cache-line
+/// ping-pong a single contended `compare_exchange` causes under multi-core
+/// contention.
 pub struct SpinLock<T> {
     val: UnsafeCell<T>,
-    lock: AtomicBool,
+    next_ticket: AtomicUsize,
+    now_serving: AtomicUsize,
 }
 
 impl<T> SpinLock<T> {
@@ -17,7 +29,8 @@ impl<T> SpinLock<T> {
     pub const fn new(val: T) -> Self {
         Self {
             val: UnsafeCell::new(val),
-            lock: AtomicBool::new(false),
+            next_ticket: AtomicUsize::new(0),
+            now_serving: AtomicUsize::new(0),
         }
     }
 
@@ -51,19 +64,33 @@ impl<T> SpinLock<T> {
 
     /// Access the spin lock, otherwise busy waiting
     fn acquire_lock(&self) {
-        while !self.try_acquire_lock() {
-            core::hint::spin_loop();
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        let mut backoff_spins = 1;
+        // Test-and-test-and-set: poll with a plain load, since a ticket we
+        // are not yet serving will not be made ours by racing on it, only
+        // by whoever is ahead of us releasing the lock.
+        while self.now_serving.load(Ordering::Acquire) != ticket {
+            for _ in 0..backoff_spins {
+                core::hint::spin_loop();
+            }
+            backoff_spins = (backoff_spins * 2).min(MAX_BACKOFF_SPINS);
         }
     }
 
     fn try_acquire_lock(&self) -> bool {
-        self.lock
-            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        self.next_ticket
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |ticket| {
+                if ticket == self.now_serving.load(Ordering::Acquire) {
+                    Some(ticket + 1)
+                } else {
+                    None
+                }
+            })
             .is_ok()
     }
 
     fn release_lock(&self) {
-        self.lock.store(false, Ordering::SeqCst);
+        self.now_serving.fetch_add(1, Ordering::Release);
     }
 }
 
@@ -0,0 +1,42 @@
+use super::signal::constants::SigNum;
+
+/// The reason a process stopped being runnable, as reported to `wait`.
+///
+/// Replaces the old raw `i32` exit code, which could not distinguish a
+/// normal `exit`/`exit_group` from death by an uncaught signal, nor report
+/// job-control stop/continue transitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// The process called `exit`/`exit_group`, or its main thread returned,
+    /// with the given 8-bit exit code.
+    Exited(u8),
+    /// The process was terminated by an uncaught, fatal signal.
+    Killed { signal: SigNum, core_dumped: bool },
+    /// The process is stopped (e.g. by `SIGSTOP`) but not dead, as reported
+    /// when the waiter passed `WUNTRACED`.
+    Stopped(SigNum),
+    /// The process was resumed by `SIGCONT`, as reported when the waiter
+    /// passed `WCONTINUED`.
+    Continued,
+}
+
+impl ExitStatus {
+    /// Encodes `self` into the packed `wstatus` integer that Linux
+    /// userspace expects from `waitpid`/`wait4`: the exit code lives in
+    /// bits 8-15, the terminating signal in the low 7 bits, and stopped/
+    /// continued processes are flagged with the `0x7f`/`0xffff` markers.
+    pub fn as_wstatus(&self) -> i32 {
+        match self {
+            ExitStatus::Exited(exit_code) => (*exit_code as i32) << 8,
+            ExitStatus::Killed {
+                signal,
+                core_dumped,
+            } => {
+                let core_dump_flag = if *core_dumped { 0x80 } else { 0 };
+                (signal.as_u8() as i32) | core_dump_flag
+            }
+            ExitStatus::Stopped(signal) => 0x7f | ((signal.as_u8() as i32) << 8),
+            ExitStatus::Continued => 0xffff,
+        }
+    }
+}
@@ -1,16 +1,19 @@
-use core::sync::atomic::{AtomicI32, Ordering};
-
+use self::exit_status::ExitStatus;
 use self::posix_thread::posix_thread_ext::PosixThreadExt;
 use self::process_group::ProcessGroup;
 use self::process_vm::user_heap::UserHeap;
 use self::process_vm::UserVm;
+use self::ptrace::PtraceState;
 use self::rlimit::ResourceLimits;
-use self::signal::constants::SIGCHLD;
+use self::seccomp::SeccompState;
+use self::session::Session;
+use self::signal::constants::{SigNum, SIGCHLD, SIGCONT, SIGHUP, SIGKILL, SIGSTOP};
 use self::signal::sig_disposition::SigDispositions;
 use self::signal::sig_queues::SigQueues;
 use self::signal::signals::kernel::KernelSignal;
 use self::signal::signals::Signal;
 use self::status::ProcessStatus;
+use self::veil::{PledgeSet, VeilState};
 use crate::device::tty::get_n_tty;
 use crate::fs::file_table::FileTable;
 use crate::fs::fs_resolver::FsResolver;
@@ -22,6 +25,7 @@ use crate::vm::vmar::Vmar;
 use jinux_frame::sync::WaitQueue;
 
 pub mod clone;
+pub mod exit_status;
 pub mod fifo_scheduler;
 pub mod posix_thread;
 pub mod process_filter;
@@ -29,9 +33,13 @@ pub mod process_group;
 pub mod process_table;
 pub mod process_vm;
 pub mod program_loader;
+pub mod ptrace;
 pub mod rlimit;
+pub mod seccomp;
+pub mod session;
 pub mod signal;
 pub mod status;
+pub mod veil;
 pub mod wait;
 
 pub type Pid = i32;
@@ -49,14 +57,17 @@ pub struct Process {
     root_vmar: Arc<Vmar<Full>>,
     /// wait for child status changed
     waiting_children: WaitQueue,
+    /// parks a traced thread inside a ptrace stop until its tracer resumes
+    /// it (`PTRACE_CONT`/`PTRACE_SYSCALL`/`PTRACE_DETACH`)
+    ptrace_stop_queue: WaitQueue,
 
     // Mutable Part
     /// The executable path.
     executable_path: RwLock<String>,
     /// The threads
     threads: Mutex<Vec<Arc<Thread>>>,
-    /// The exit code
-    exit_code: AtomicI32,
+    /// The exit status, set once the process has exited
+    exit_status: Mutex<Option<ExitStatus>>,
     /// Process status
     status: Mutex<ProcessStatus>,
     /// Parent process
@@ -65,6 +76,23 @@ pub struct Process {
     children: Mutex<BTreeMap<Pid, Arc<Process>>>,
     /// Process group
     process_group: Mutex<Weak<ProcessGroup>>,
+    /// Session
+    session: Mutex<Weak<Session>>,
+    /// The process that is tracing this process via ptrace, if any
+    tracer: Mutex<Weak<Process>>,
+    /// The processes this process is tracing via ptrace, if any
+    tracees: Mutex<Vec<Weak<Process>>>,
+    /// This process's ptrace state as a tracee
+    ptrace: Mutex<PtraceState>,
+    /// The signal intercepted by the process's current `SignalDeliveryStop`,
+    /// if any, held here until the tracer resumes it
+    ptrace_pending_signal: Mutex<Option<Box<dyn Signal>>>,
+    /// Installed seccomp-BPF filters, shared across a thread group
+    seccomp: Mutex<SeccompState>,
+    /// Unveil (path allow-list) sandbox state
+    veil: RwLock<VeilState>,
+    /// Pledge: the set of syscall classes the process is allowed to use
+    promises: RwLock<PledgeSet>,
     /// File table
     file_table: Arc<Mutex<FileTable>>,
     /// FsResolver
@@ -108,6 +136,11 @@ impl Process {
     ) -> Self {
         let children = BTreeMap::new();
         let waiting_children = WaitQueue::new();
+        let ptrace_stop_queue = WaitQueue::new();
+        // A child inherits its parent's seccomp mode and filters.
+        let seccomp = parent
+            .upgrade()
+            .map_or_else(SeccompState::new, |parent| parent.seccomp().lock().clone());
         let resource_limits = ResourceLimits::default();
         Self {
             pid,
@@ -116,11 +149,20 @@ impl Process {
             user_vm,
             root_vmar,
             waiting_children,
-            exit_code: AtomicI32::new(0),
+            ptrace_stop_queue,
+            exit_status: Mutex::new(None),
             status: Mutex::new(ProcessStatus::Runnable),
             parent: Mutex::new(parent),
             children: Mutex::new(children),
             process_group: Mutex::new(process_group),
+            session: Mutex::new(Weak::new()),
+            tracer: Mutex::new(Weak::new()),
+            tracees: Mutex::new(Vec::new()),
+            ptrace: Mutex::new(PtraceState::new()),
+            ptrace_pending_signal: Mutex::new(None),
+            seccomp: Mutex::new(seccomp),
+            veil: RwLock::new(VeilState::new()),
+            promises: RwLock::new(PledgeSet::all()),
             file_table,
             fs,
             umask,
@@ -134,6 +176,12 @@ impl Process {
         &self.waiting_children
     }
 
+    /// The queue a traced thread parks on while it is in a ptrace stop,
+    /// woken once its tracer resumes it.
+    pub fn ptrace_stop_queue(&self) -> &WaitQueue {
+        &self.ptrace_stop_queue
+    }
+
     /// init a user process and run the process
     pub fn spawn_user_process(
         executable_path: &str,
@@ -142,12 +190,11 @@ impl Process {
     ) -> Result<Arc<Self>> {
         // spawn user process should give an absolute path
         debug_assert!(executable_path.starts_with('/'));
-        let process = Process::create_user_process(executable_path, argv, envp)?;
+        let (process, session) = Process::create_user_process(executable_path, argv, envp)?;
         // FIXME: How to determine the fg process group?
         let pgid = process.pgid();
         // FIXME: tty should be a parameter?
-        let tty = get_n_tty();
-        tty.set_fg(pgid);
+        session.set_controlling_terminal(get_n_tty(), pgid);
         process.run();
         Ok(process)
     }
@@ -156,7 +203,7 @@ impl Process {
         executable_path: &str,
         argv: Vec<CString>,
         envp: Vec<CString>,
-    ) -> Result<Arc<Self>> {
+    ) -> Result<(Arc<Self>, Arc<Session>)> {
         let root_vmar = Vmar::<Full>::new_root()?;
         let fs = FsResolver::new();
         let umask = FileCreationMask::default();
@@ -191,10 +238,10 @@ impl Process {
         )?;
         user_process.threads().lock().push(thread);
 
-        // Set process group
-        user_process.create_and_set_process_group();
+        // Set session and process group
+        let session = user_process.create_and_set_session_and_group();
         process_table::add_process(user_process.clone());
-        Ok(user_process)
+        Ok((user_process, session))
     }
 
     /// returns the pid of the process
@@ -215,6 +262,40 @@ impl Process {
         &self.process_group
     }
 
+    /// returns the session id of the process
+    pub fn sid(&self) -> Pid {
+        if let Some(session) = self.session.lock().upgrade() {
+            session.sid()
+        } else {
+            0
+        }
+    }
+
+    pub fn session(&self) -> &Mutex<Weak<Session>> {
+        &self.session
+    }
+
+    /// Set session for current process. If old session exists,
+    /// remove current process's process group from it, once the process
+    /// group itself has no other members left in that session.
+    pub fn set_session(&self, session: Weak<Session>) {
+        if let Some(old_session) = self.session.lock().upgrade() {
+            if let Some(old_process_group) = self.process_group.lock().upgrade() {
+                // `self` is still counted as a member here (this runs
+                // before `set_process_group` detaches it below), so the
+                // group only becomes empty, and thus unreachable from the
+                // old session, once `self` was its last member.
+                // `sys_setsid` only succeeds for non-leaders, so the old
+                // group commonly still has live members that must remain
+                // reachable via the old session's process_groups map.
+                if old_process_group.processes().lock().len() <= 1 {
+                    old_session.remove_process_group(old_process_group.pgid());
+                }
+            }
+        }
+        *self.session.lock() = session;
+    }
+
     /// add a child process
     pub fn add_child(&self, child: Arc<Process>) {
         let child_pid = child.pid();
@@ -246,13 +327,27 @@ impl Process {
         &self.umask
     }
 
-    /// create a new process group for the process and add it to globle table.
-    /// Then set the process group for current process.
-    fn create_and_set_process_group(self: &Arc<Self>) {
+    /// create a new session led by the process, along with a new process
+    /// group (also led by the process) that belongs to it, and add both
+    /// to the global tables. Then set them for the current process.
+    ///
+    /// Returns the new session as a strong `Arc`, so callers that need it
+    /// right away (e.g. to set a controlling terminal) don't have to
+    /// re-derive it from `self.session()`'s `Weak`, which would panic on
+    /// `.upgrade()` if the session were ever dropped in between.
+    fn create_and_set_session_and_group(self: &Arc<Self>) -> Arc<Session> {
+        let session = Session::new(self);
         let process_group = Arc::new(ProcessGroup::new(self.clone()));
-        let pgid = process_group.pgid();
+        session.add_process_group(process_group.clone());
+        // `set_session` must run before `set_process_group`: it detaches the
+        // *old* process group from the old session using `self.pgid()`,
+        // which would already report the new group if the order were
+        // reversed.
+        self.set_session(Arc::downgrade(&session));
         self.set_process_group(Arc::downgrade(&process_group));
         process_table::add_process_group(process_group);
+        process_table::add_session(session.clone());
+        session
     }
 
     pub fn parent(&self) -> Option<Arc<Process>> {
@@ -260,13 +355,28 @@ impl Process {
     }
 
     /// Exit thread group(the process).
-    /// Set the status of the process as Zombie and set exit code.
+    /// Set the status of the process as Zombie and set exit status.
     /// Move all children to init process.
     /// Wake up the parent wait queue if parent is waiting for self.
-    pub fn exit_group(&self, exit_code: i32) {
+    ///
+    /// `fatal_signal` should be `Some` when the exit was caused by an
+    /// uncaught signal rather than `exit`/`exit_group`, so the correct
+    /// `ExitStatus` variant can be recorded for `wait`.
+    pub fn exit_group(&self, exit_code: i32, fatal_signal: Option<(SigNum, bool)>) {
         debug!("exit group was called");
+        let exit_status = match fatal_signal {
+            Some((signal, core_dumped)) => ExitStatus::Killed {
+                signal,
+                core_dumped,
+            },
+            None => ExitStatus::Exited(exit_code as u8),
+        };
+        // `exit_status` must be set *before* the status flips to zombie: a
+        // waiter (possibly a `WNOHANG` poller racing this very call) may
+        // reap the child as soon as it observes the zombie status, and
+        // `reap_zombie_child` expects an exit status to already be there.
+        *self.exit_status.lock() = Some(exit_status);
         self.status.lock().set_zombie();
-        self.exit_code.store(exit_code, Ordering::Relaxed);
         for thread in &*self.threads.lock() {
             thread.exit();
         }
@@ -287,6 +397,29 @@ impl Process {
             // wake up parent waiting children, if any
             parent.waiting_children().wake_all();
         }
+
+        // If this process is being traced by a process other than its
+        // parent, the tracer also needs to be woken up to reap the event.
+        if let Some(tracer) = self.tracer.lock().upgrade() {
+            if self.parent().map_or(true, |parent| !Arc::ptr_eq(&parent, &tracer)) {
+                let signal = Box::new(KernelSignal::new(SIGCHLD));
+                tracer.sig_queues().lock().enqueue(signal);
+                tracer.waiting_children().wake_all();
+            }
+        }
+
+        // If this process is the session leader, relinquish the controlling
+        // terminal and hang up the foreground process group.
+        if let Some(session) = self.session.lock().upgrade() {
+            if session.sid() == self.pid {
+                if let Some(fg_group) = session.foreground_process_group() {
+                    for process in fg_group.processes().lock().values() {
+                        process.enqueue_signal(Box::new(KernelSignal::new(SIGHUP)));
+                    }
+                }
+                session.release_controlling_terminal();
+            }
+        }
     }
 
     /// if the current process is init process
@@ -324,10 +457,15 @@ impl Process {
         self.user_vm.user_heap()
     }
 
-    /// free zombie child with pid, returns the exit code of child process.
-    /// remove process from process group.
-    pub fn reap_zombie_child(&self, pid: Pid) -> i32 {
-        let child_process = self.children.lock().remove(&pid).unwrap();
+    /// Frees the zombie child with `pid`, returning its exit status, and
+    /// removes it from the process table and its process group.
+    ///
+    /// Returns `None` if `pid` is no longer among this process's children:
+    /// two threads of the same parent may both observe the same zombie
+    /// before either reaps it (`wait`/`waitpid` run concurrently), so the
+    /// loser must back off instead of panicking.
+    pub fn reap_zombie_child(&self, pid: Pid) -> Option<ExitStatus> {
+        let child_process = self.children.lock().remove(&pid)?;
         assert!(child_process.status().lock().is_zombie());
         child_process.root_vmar().destroy_all().unwrap();
         for thread in &*child_process.threads.lock() {
@@ -337,15 +475,21 @@ impl Process {
         if let Some(process_group) = child_process.process_group().lock().upgrade() {
             process_group.remove_process(child_process.pid);
         }
-        child_process.exit_code().load(Ordering::SeqCst)
+        Some(
+            child_process
+                .exit_status()
+                .lock()
+                .take()
+                .expect("a reaped zombie child must have an exit status"),
+        )
     }
 
     pub fn children(&self) -> &Mutex<BTreeMap<Pid, Arc<Process>>> {
         &self.children
     }
 
-    pub fn exit_code(&self) -> &AtomicI32 {
-        &self.exit_code
+    pub fn exit_status(&self) -> &Mutex<Option<ExitStatus>> {
+        &self.exit_status
     }
 
     /// whether the process has child process
@@ -374,9 +518,55 @@ impl Process {
     }
 
     pub fn enqueue_signal(&self, signal: Box<dyn Signal>) {
-        if !self.status().lock().is_zombie() {
+        if self.status().lock().is_zombie() {
+            return;
+        }
+        // SIGSTOP and SIGCONT are job-control signals: they are never
+        // handler-catchable, so instead of going through the normal signal
+        // queue they flip this process's scheduling status directly. This
+        // is exactly what `wait`'s `WUNTRACED`/`WCONTINUED` options observe.
+        match signal.num() {
+            SIGSTOP => {
+                self.status.lock().stop(SIGSTOP);
+                self.notify_parent_of_status_change();
+                return;
+            }
+            SIGCONT => {
+                if self.status.lock().resume_from_stop() {
+                    self.notify_parent_of_status_change();
+                }
+                return;
+            }
+            _ => {}
+        }
+        // SIGKILL can never be intercepted by a tracer, just as real ptrace
+        // cannot block it: otherwise a tracer that never calls PTRACE_CONT
+        // (or simply hangs) would make the tracee permanently unkillable.
+        if signal.num() != SIGKILL {
+            // A traced process has its signal deliveries intercepted: the
+            // tracer observes (and may alter) the signal via `wait`/`ptrace`
+            // before it is ever queued for normal delivery.
+            let Some(signal) = self.ptrace_signal_delivery_stop(signal) else {
+                return;
+            };
             self.sig_queues.lock().enqueue(signal);
+            return;
         }
+        self.sig_queues.lock().enqueue(signal);
+    }
+
+    /// Notifies the parent of a job-control stop/continue transition: sends
+    /// `SIGCHLD` and wakes any blocked `wait`/`waitpid`, mirroring how
+    /// `exit_group` notifies the parent of a zombie transition.
+    fn notify_parent_of_status_change(&self) {
+        let Some(parent) = self.parent() else {
+            return;
+        };
+        parent
+            .sig_queues()
+            .lock()
+            .enqueue(Box::new(KernelSignal::new(SIGCHLD)));
+        parent.waiting_children().wake_all();
     }
 }
 
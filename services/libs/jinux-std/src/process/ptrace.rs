@@ -0,0 +1,384 @@
+use alloc::collections::VecDeque;
+
+use super::exit_status::ExitStatus;
+use super::signal::constants::{SigNum, SIGCHLD, SIGTRAP};
+use super::signal::signals::kernel::KernelSignal;
+use super::signal::signals::Signal;
+use super::{Pid, Process};
+use crate::prelude::*;
+use crate::syscall::SyscallReturn;
+
+const PTRACE_TRACEME: i32 = 0;
+const PTRACE_PEEKTEXT: i32 = 1;
+const PTRACE_PEEKDATA: i32 = 2;
+const PTRACE_POKETEXT: i32 = 4;
+const PTRACE_POKEDATA: i32 = 5;
+const PTRACE_CONT: i32 = 7;
+const PTRACE_KILL: i32 = 8;
+const PTRACE_GETREGS: i32 = 12;
+const PTRACE_SETREGS: i32 = 13;
+const PTRACE_ATTACH: i32 = 16;
+const PTRACE_DETACH: i32 = 17;
+const PTRACE_SYSCALL: i32 = 24;
+const PTRACE_SEIZE: i32 = 0x4206;
+
+/// The stop state of a traced thread, as observed by its tracer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopState {
+    /// The tracee is running (or runnable) normally.
+    Running,
+    /// The tracee stopped right before `signal` would have been delivered.
+    SignalDeliveryStop(SigNum),
+    /// The tracee stopped at the entry of a syscall (PTRACE_SYSCALL).
+    SyscallEnterStop,
+    /// The tracee stopped at the exit of a syscall (PTRACE_SYSCALL).
+    SyscallExitStop,
+    /// The tracee stopped because of a ptrace event (e.g. PTRACE_EVENT_EXIT).
+    EventStop(PtraceEvent),
+}
+
+/// A ptrace event that caused an `EventStop`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PtraceEvent {
+    Fork,
+    Vfork,
+    Clone,
+    Exec,
+    Exit,
+}
+
+/// Per-process ptrace state of a tracee: its ptrace options, the stop it is
+/// currently sitting in, if any, and the stops not yet reported. Whether the
+/// process is traced at all is recorded by `Process::tracer`.
+#[derive(Debug)]
+pub struct PtraceState {
+    options: i32,
+    stop_state: StopState,
+    /// Signal-delivery-stops, syscall-stops and event-stops that have not
+    /// yet been observed by the tracer via `wait`.
+    pending_events: VecDeque<StopState>,
+    /// Set by `PTRACE_SYSCALL` (cleared by `PTRACE_CONT`/`PTRACE_DETACH`):
+    /// the tracee should stop at the next syscall-entry/exit boundary
+    /// instead of running freely until its next signal.
+    syscall_trace_armed: bool,
+    /// Whether the next `syscall_trace_stop` is for syscall entry (`true`)
+    /// or exit (`false`); toggled each time the tracee actually stops,
+    /// matching `PTRACE_SYSCALL`'s alternating entry/exit stops, and reset
+    /// to entry when syscall tracing is disarmed.
+    next_syscall_stop_is_entry: bool,
+}
+
+impl PtraceState {
+    pub fn new() -> Self {
+        Self {
+            options: 0,
+            stop_state: StopState::Running,
+            pending_events: VecDeque::new(),
+            syscall_trace_armed: false,
+            next_syscall_stop_is_entry: true,
+        }
+    }
+
+    /// Whether the tracee is in a ptrace stop or has stops its tracer has
+    /// not yet observed. A traced process that is running with nothing
+    /// pending returns `false`; see `Process::tracer` for the tracer itself.
+    pub fn is_traced(&self) -> bool {
+        self.stop_state != StopState::Running || !self.pending_events.is_empty()
+    }
+
+    pub fn stop_state(&self) -> StopState {
+        self.stop_state
+    }
+
+    pub fn enter_stop(&mut self, stop_state: StopState) {
+        self.stop_state = stop_state;
+        self.pending_events.push_back(stop_state);
+    }
+
+    pub fn resume(&mut self) {
+        self.stop_state = StopState::Running;
+    }
+
+    /// Arms or disarms syscall-entry/exit stops for `PTRACE_SYSCALL`
+    /// (`true`) vs. `PTRACE_CONT`/`PTRACE_DETACH` (`false`).
+    pub fn set_syscall_trace_armed(&mut self, armed: bool) {
+        self.syscall_trace_armed = armed;
+        if !armed {
+            self.next_syscall_stop_is_entry = true;
+        }
+    }
+
+    pub fn syscall_trace_armed(&self) -> bool {
+        self.syscall_trace_armed
+    }
+
+    /// Returns the stop to enter for the next syscall boundary and flips
+    /// which boundary (entry/exit) is next, alternating on every call as
+    /// real `PTRACE_SYSCALL` stops do.
+    fn take_next_syscall_stop(&mut self) -> StopState {
+        let stop = if self.next_syscall_stop_is_entry {
+            StopState::SyscallEnterStop
+        } else {
+            StopState::SyscallExitStop
+        };
+        self.next_syscall_stop_is_entry = !self.next_syscall_stop_is_entry;
+        stop
+    }
+
+    pub fn set_options(&mut self, options: i32) {
+        self.options = options;
+    }
+
+    pub fn options(&self) -> i32 {
+        self.options
+    }
+
+    pub fn pop_pending_event(&mut self) -> Option<StopState> {
+        self.pending_events.pop_front()
+    }
+}
+
+impl Default for PtraceState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The signal `wait`/`waitpid` should report for a given ptrace stop.
+/// `SignalDeliveryStop` reports the intercepted signal itself; all other
+/// stops (syscall-stops, event-stops) are reported as `SIGTRAP`, matching
+/// how Linux encodes them in `WSTOPSIG`.
+fn stop_signal(stop_state: StopState) -> SigNum {
+    match stop_state {
+        StopState::SignalDeliveryStop(signal) => signal,
+        StopState::Running | StopState::SyscallEnterStop | StopState::SyscallExitStop => SIGTRAP,
+        StopState::EventStop(_) => SIGTRAP,
+    }
+}
+
+impl Process {
+    /// Called when `signal` is about to be delivered to this process. If
+    /// the process is traced, the delivery is intercepted: the process
+    /// enters a `SignalDeliveryStop`, the tracer is notified via `SIGCHLD`
+    /// and woken up so `wait` can observe the stop, and the signal is not
+    /// delivered until the tracer resumes it (`PTRACE_CONT`/
+    /// `PTRACE_SYSCALL`/`PTRACE_DETACH`).
+    ///
+    /// `signal` is held onto (see `ptrace_pending_signal`) rather than
+    /// dropped, so that `redeliver_ptrace_signal` can put it back on the
+    /// normal delivery path, unchanged or substituted, once the tracer
+    /// resumes the tracee.
+    ///
+    /// Only the tracee's own thread can actually park here: `enqueue_signal`
+    /// is sometimes called on a process other than the current one (e.g.
+    /// session-leader exit fanning `SIGHUP` out to other session members),
+    /// and blocking that unrelated caller would stall code that has nothing
+    /// to do with the traced process. In that case the stop is still
+    /// recorded and reported to the tracer, but the actual park is left to
+    /// the tracee's own execution the next time it observes the stop.
+    ///
+    /// Returns `None` if the signal was intercepted, `Some(signal)`
+    /// (handed back unchanged) if the process is not traced.
+    pub fn ptrace_signal_delivery_stop(&self, signal: Box<dyn Signal>) -> Option<Box<dyn Signal>> {
+        let tracer = self.tracer.lock().upgrade();
+        let Some(tracer) = tracer else {
+            return Some(signal);
+        };
+        let signal_num = signal.num();
+        *self.ptrace_pending_signal.lock() = Some(signal);
+        self.ptrace
+            .lock()
+            .enter_stop(StopState::SignalDeliveryStop(signal_num));
+        tracer
+            .sig_queues()
+            .lock()
+            .enqueue(Box::new(KernelSignal::new(SIGCHLD)));
+        tracer.waiting_children().wake_all();
+
+        if self.pid() == current!().pid() {
+            self.ptrace_stop_queue.wait_until(|| {
+                (self.ptrace.lock().stop_state() == StopState::Running).then_some(())
+            });
+        }
+        None
+    }
+
+    /// Consumes this tracee's pending signal-delivery-stop signal, if any,
+    /// and puts it back on the normal delivery path, honoring a tracer's
+    /// substitution.
+    ///
+    /// `resume_signal` is the `data` argument to `PTRACE_CONT`/
+    /// `PTRACE_SYSCALL`/`PTRACE_DETACH`: `0` suppresses the signal
+    /// entirely (matching Linux, where `PTRACE_CONT(pid, 0)` drops it),
+    /// the originally intercepted signal number re-delivers the same
+    /// signal unchanged, and any other signal number substitutes a
+    /// different one in its place.
+    pub fn redeliver_ptrace_signal(&self, resume_signal: SigNum) {
+        let Some(pending) = self.ptrace_pending_signal.lock().take() else {
+            return;
+        };
+        if resume_signal == 0 {
+            return;
+        }
+        let signal: Box<dyn Signal> = if resume_signal == pending.num() {
+            pending
+        } else {
+            Box::new(KernelSignal::new(resume_signal))
+        };
+        self.sig_queues.lock().enqueue(signal);
+    }
+
+    /// Called at a syscall entry or exit boundary. If `PTRACE_SYSCALL`
+    /// armed syscall tracing for this tracee, it stops here (alternating
+    /// entry/exit stops, like real `PTRACE_SYSCALL`), notifies the tracer
+    /// via `SIGCHLD`, and parks until the tracer resumes it with
+    /// `PTRACE_CONT`/`PTRACE_SYSCALL`/`PTRACE_DETACH`. A no-op if syscall
+    /// tracing is not armed.
+    ///
+    /// The syscall-entry/exit trap that would call this on every syscall
+    /// lives in `crate::syscall` (outside this slice of the tree, which
+    /// only covers `jinux-std/src/process`) and does not call this yet.
+    pub fn ptrace_syscall_stop(&self) {
+        let Some(tracer) = self.tracer.lock().upgrade() else {
+            return;
+        };
+        let stop = {
+            let mut ptrace = self.ptrace.lock();
+            if !ptrace.syscall_trace_armed() {
+                return;
+            }
+            ptrace.take_next_syscall_stop()
+        };
+        self.ptrace.lock().enter_stop(stop);
+        tracer
+            .sig_queues()
+            .lock()
+            .enqueue(Box::new(KernelSignal::new(SIGCHLD)));
+        tracer.waiting_children().wake_all();
+
+        if self.pid() == current!().pid() {
+            self.ptrace_stop_queue.wait_until(|| {
+                (self.ptrace.lock().stop_state() == StopState::Running).then_some(())
+            });
+        }
+    }
+
+    pub fn tracer(&self) -> &Mutex<Weak<Process>> {
+        &self.tracer
+    }
+
+    pub fn ptrace(&self) -> &Mutex<PtraceState> {
+        &self.ptrace
+    }
+
+    /// The processes currently being traced by this process.
+    pub fn tracees(&self) -> &Mutex<Vec<Weak<Process>>> {
+        &self.tracees
+    }
+
+    /// Reports the next unobserved ptrace stop among this process's tracees
+    /// (or, if `pid` is given, the tracee with that pid), as `wait` should
+    /// surface it.
+    ///
+    /// Unlike `self.children()`, a tracee attached via `PTRACE_ATTACH`/
+    /// `PTRACE_SEIZE` need not be a child of the tracer, so `wait_for_child`
+    /// must consult this separately to let a debugger observe the stops of
+    /// a process it attached to but did not fork.
+    pub fn take_tracee_stop(self: &Arc<Self>, pid: Option<Pid>) -> Option<(Pid, ExitStatus)> {
+        let tracees = self.tracees.lock();
+        for tracee in tracees.iter() {
+            let Some(tracee) = tracee.upgrade() else {
+                continue;
+            };
+            if pid.is_some_and(|pid| pid != tracee.pid()) {
+                continue;
+            }
+            if let Some(stop_state) = tracee.ptrace.lock().pop_pending_event() {
+                return Some((tracee.pid(), ExitStatus::Stopped(stop_signal(stop_state))));
+            }
+        }
+        None
+    }
+
+    fn set_tracer(self: &Arc<Self>, tracer: Weak<Process>) {
+        if let Some(old_tracer) = self.tracer.lock().upgrade() {
+            old_tracer
+                .tracees
+                .lock()
+                .retain(|tracee| !Weak::ptr_eq(&Arc::downgrade(self), tracee));
+        }
+        if let Some(new_tracer) = tracer.upgrade() {
+            new_tracer.tracees.lock().push(Arc::downgrade(self));
+        }
+        *self.tracer.lock() = tracer;
+    }
+}
+
+/// Handles the `ptrace` syscall.
+pub fn sys_ptrace(request: i32, pid: Pid, addr: Vaddr, data: Vaddr) -> Result<SyscallReturn> {
+    debug!(
+        "request = {}, pid = {}, addr = 0x{:x}, data = 0x{:x}",
+        request, pid, addr, data
+    );
+    // `ptrace` is the textbook "proc" operation: it inspects or controls
+    // another process's execution. A pledged process that dropped `proc`
+    // loses access to all of it, tracer and tracee side alike.
+    current!().check_promise(super::veil::PledgeSet::PROC)?;
+    match request {
+        PTRACE_TRACEME => {
+            let current = current!();
+            let parent = current
+                .parent()
+                .ok_or_else(|| Error::with_message(Errno::EPERM, "the process has no parent"))?;
+            current.set_tracer(Arc::downgrade(&parent));
+            Ok(SyscallReturn::Return(0))
+        }
+        PTRACE_ATTACH | PTRACE_SEIZE => {
+            let tracee = super::process_table::pid_to_process(pid)
+                .ok_or_else(|| Error::with_message(Errno::ESRCH, "no such process"))?;
+            let tracer = current!();
+            tracee.set_tracer(Arc::downgrade(&tracer));
+            Ok(SyscallReturn::Return(0))
+        }
+        PTRACE_CONT | PTRACE_SYSCALL => {
+            let tracee = super::process_table::pid_to_process(pid)
+                .ok_or_else(|| Error::with_message(Errno::ESRCH, "no such process"))?;
+            // `data` is the signal to redeliver (0 to suppress it), exactly
+            // as `PTRACE_CONT`/`PTRACE_SYSCALL` are documented to accept.
+            tracee.redeliver_ptrace_signal(data as SigNum);
+            // `PTRACE_SYSCALL` additionally arms a stop at the next
+            // syscall-entry/exit boundary (see `ptrace_syscall_stop`);
+            // `PTRACE_CONT` disarms it, letting the tracee run freely
+            // until its next signal.
+            tracee
+                .ptrace()
+                .lock()
+                .set_syscall_trace_armed(request == PTRACE_SYSCALL);
+            tracee.ptrace().lock().resume();
+            tracee.ptrace_stop_queue().wake_all();
+            Ok(SyscallReturn::Return(0))
+        }
+        PTRACE_GETREGS | PTRACE_SETREGS => {
+            // Reading/writing the tracee's registers needs a per-thread
+            // CPU/user-context type that isn't part of this slice of the
+            // tree (it would live on `posix_thread::PosixThread`, which
+            // isn't included here); there is nothing here to read or write
+            // into, so this remains unimplemented rather than faked.
+            return_errno_with_message!(Errno::ENOSYS, "this ptrace request is not supported yet")
+        }
+        PTRACE_DETACH => {
+            let tracee = super::process_table::pid_to_process(pid)
+                .ok_or_else(|| Error::with_message(Errno::ESRCH, "no such process"))?;
+            tracee.redeliver_ptrace_signal(data as SigNum);
+            tracee.ptrace().lock().set_syscall_trace_armed(false);
+            tracee.set_tracer(Weak::new());
+            tracee.ptrace().lock().resume();
+            tracee.ptrace_stop_queue().wake_all();
+            Ok(SyscallReturn::Return(0))
+        }
+        PTRACE_PEEKTEXT | PTRACE_PEEKDATA | PTRACE_POKETEXT | PTRACE_POKEDATA | PTRACE_KILL => {
+            return_errno_with_message!(Errno::ENOSYS, "this ptrace request is not supported yet")
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "unknown ptrace request"),
+    }
+}
@@ -0,0 +1,444 @@
+use super::signal::constants::{SIGKILL, SIGSYS};
+use super::signal::signals::kernel::KernelSignal;
+use super::Process;
+use crate::prelude::*;
+use crate::syscall::SyscallReturn;
+
+const SECCOMP_SET_MODE_STRICT: i32 = 0;
+const SECCOMP_SET_MODE_FILTER: i32 = 1;
+
+const PR_SET_SECCOMP: i32 = 22;
+const PR_SET_NO_NEW_PRIVS: i32 = 38;
+const PR_GET_NO_NEW_PRIVS: i32 = 39;
+
+const SECCOMP_MODE_FILTER: i32 = 2;
+
+/// The seccomp mode a process is running under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeccompMode {
+    /// No syscall filtering is installed.
+    Disabled,
+    /// SECCOMP_MODE_STRICT: only `read`, `write`, `_exit`/`exit_group` and
+    /// `rt_sigreturn` are permitted; anything else kills the process.
+    Strict,
+    /// SECCOMP_MODE_FILTER: syscalls are screened by the installed BPF
+    /// programs.
+    Filter,
+}
+
+/// The disposition cBPF evaluation settled on for a syscall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SeccompAction {
+    Allow,
+    Errno(i32),
+    Trap,
+    KillThread,
+    KillProcess,
+}
+
+impl SeccompAction {
+    /// Action priority: the effective action across all installed filters is
+    /// the highest-priority one, with kill being the strictest.
+    fn priority(&self) -> u8 {
+        match self {
+            SeccompAction::Allow => 0,
+            SeccompAction::Errno(_) => 1,
+            SeccompAction::Trap => 2,
+            SeccompAction::KillThread => 3,
+            SeccompAction::KillProcess => 4,
+        }
+    }
+}
+
+/// The syscall arguments presented to a cBPF filter, mirroring Linux's
+/// `struct seccomp_data`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SeccompData {
+    pub nr: i32,
+    pub arch: u32,
+    pub instruction_pointer: u64,
+    pub args: [u64; 6],
+}
+
+/// A single cBPF instruction (classic/socket-filter format).
+#[derive(Debug, Clone, Copy)]
+pub struct BpfInstruction {
+    pub code: u16,
+    pub jt: u8,
+    pub jf: u8,
+    pub k: u32,
+}
+
+/// A BPF program installed via `seccomp(SECCOMP_SET_MODE_FILTER, ...)`.
+/// Programs are immutable once installed.
+#[derive(Debug, Clone)]
+pub struct SeccompFilter {
+    program: Vec<BpfInstruction>,
+}
+
+impl SeccompFilter {
+    pub fn new(program: Vec<BpfInstruction>) -> Self {
+        Self { program }
+    }
+
+    /// Interprets the program over `data` using a simple accumulator/scratch
+    /// machine, returning the resulting seccomp action.
+    pub fn evaluate(&self, data: &SeccompData) -> SeccompAction {
+        let words = seccomp_data_words(data);
+        let mut accumulator: u32 = 0;
+        let mut scratch = [0u32; 16];
+        let mut pc = 0usize;
+
+        while let Some(instruction) = self.program.get(pc) {
+            // Classic BPF opcode classes (low 3 bits of `code`).
+            match instruction.code & 0x07 {
+                0x00 => {
+                    // BPF_LD: load a word from the seccomp_data into the accumulator.
+                    let word_index = (instruction.k / 4) as usize;
+                    accumulator = *words.get(word_index).unwrap_or(&0);
+                    pc += 1;
+                }
+                0x01 => {
+                    // BPF_LDX / scratch load, reusing `k` as the scratch index.
+                    accumulator = scratch[(instruction.k as usize) % scratch.len()];
+                    pc += 1;
+                }
+                0x02 => {
+                    // BPF_ST: store the accumulator into a scratch slot.
+                    scratch[(instruction.k as usize) % scratch.len()] = accumulator;
+                    pc += 1;
+                }
+                0x05 => {
+                    // BPF_JMP: dispatch on the real jump subtype (high nibble
+                    // of `code`) instead of always treating it as BPF_JEQ.
+                    if instruction.code & 0x08 != 0 {
+                        // BPF_X: this machine has no dedicated X register
+                        // (unlike real cBPF), so a filter that jumps against
+                        // X cannot be evaluated correctly. Fail closed,
+                        // same as a genuinely unsupported opcode class.
+                        return SeccompAction::KillThread;
+                    }
+                    match instruction.code & 0xf0 {
+                        0x00 => {
+                            // BPF_JA: unconditional jump, offset in `k`.
+                            pc += 1 + instruction.k as usize;
+                        }
+                        0x10 => {
+                            // BPF_JEQ
+                            if accumulator == instruction.k {
+                                pc += 1 + instruction.jt as usize;
+                            } else {
+                                pc += 1 + instruction.jf as usize;
+                            }
+                        }
+                        0x20 => {
+                            // BPF_JGT
+                            if accumulator > instruction.k {
+                                pc += 1 + instruction.jt as usize;
+                            } else {
+                                pc += 1 + instruction.jf as usize;
+                            }
+                        }
+                        0x30 => {
+                            // BPF_JGE
+                            if accumulator >= instruction.k {
+                                pc += 1 + instruction.jt as usize;
+                            } else {
+                                pc += 1 + instruction.jf as usize;
+                            }
+                        }
+                        0x40 => {
+                            // BPF_JSET
+                            if accumulator & instruction.k != 0 {
+                                pc += 1 + instruction.jt as usize;
+                            } else {
+                                pc += 1 + instruction.jf as usize;
+                            }
+                        }
+                        _ => {
+                            // Unrecognized jump subtype: fail closed.
+                            return SeccompAction::KillThread;
+                        }
+                    }
+                }
+                0x06 => {
+                    // BPF_RET: `k` encodes the seccomp action and, for
+                    // SECCOMP_RET_ERRNO, the errno in its low 16 bits.
+                    return decode_seccomp_ret(instruction.k);
+                }
+                _ => {
+                    // Unsupported opcode class: fail closed.
+                    return SeccompAction::KillThread;
+                }
+            }
+        }
+
+        // Falling off the end of the program without a BPF_RET is treated
+        // as an implicit kill, matching the kernel's fail-closed behavior.
+        SeccompAction::KillThread
+    }
+}
+
+/// `struct seccomp_data` is 16 words (64 bytes): `nr`, `arch`,
+/// `instruction_pointer` (2 words) and all 6 `args` (2 words each), not just
+/// the first 3 — a filter that loads `args[3]`/`args[4]`/`args[5]` (a
+/// syscall's 4th-6th argument, e.g. `mmap`'s `flags`) needs those words too.
+fn seccomp_data_words(data: &SeccompData) -> [u32; 16] {
+    [
+        data.nr as u32,
+        data.arch,
+        data.instruction_pointer as u32,
+        (data.instruction_pointer >> 32) as u32,
+        data.args[0] as u32,
+        (data.args[0] >> 32) as u32,
+        data.args[1] as u32,
+        (data.args[1] >> 32) as u32,
+        data.args[2] as u32,
+        (data.args[2] >> 32) as u32,
+        data.args[3] as u32,
+        (data.args[3] >> 32) as u32,
+        data.args[4] as u32,
+        (data.args[4] >> 32) as u32,
+        data.args[5] as u32,
+        (data.args[5] >> 32) as u32,
+    ]
+}
+
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_KILL_THREAD: u32 = 0x0000_0000;
+const SECCOMP_RET_TRAP: u32 = 0x0003_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+const SECCOMP_RET_ACTION_MASK: u32 = 0xffff_0000;
+const SECCOMP_RET_DATA_MASK: u32 = 0x0000_ffff;
+
+fn decode_seccomp_ret(k: u32) -> SeccompAction {
+    match k & SECCOMP_RET_ACTION_MASK {
+        SECCOMP_RET_ALLOW => SeccompAction::Allow,
+        SECCOMP_RET_ERRNO => SeccompAction::Errno((k & SECCOMP_RET_DATA_MASK) as i32),
+        SECCOMP_RET_TRAP => SeccompAction::Trap,
+        SECCOMP_RET_KILL_PROCESS => SeccompAction::KillProcess,
+        _ => SeccompAction::KillThread,
+    }
+}
+
+/// Per-thread-group seccomp state. Shared across a thread group and
+/// inherited across clone/exec.
+#[derive(Debug, Clone)]
+pub struct SeccompState {
+    mode: SeccompMode,
+    filters: Vec<Arc<SeccompFilter>>,
+    no_new_privs: bool,
+}
+
+impl SeccompState {
+    pub fn new() -> Self {
+        Self {
+            mode: SeccompMode::Disabled,
+            filters: Vec::new(),
+            no_new_privs: false,
+        }
+    }
+
+    pub fn mode(&self) -> SeccompMode {
+        self.mode
+    }
+
+    pub fn no_new_privs(&self) -> bool {
+        self.no_new_privs
+    }
+
+    pub fn set_no_new_privs(&mut self) {
+        self.no_new_privs = true;
+    }
+
+    /// Installs a new filter program, appended after all previously
+    /// installed filters.
+    pub fn add_filter(&mut self, filter: SeccompFilter) -> Result<()> {
+        if !self.no_new_privs {
+            return_errno_with_message!(
+                Errno::EACCES,
+                "seccomp filters require PR_SET_NO_NEW_PRIVS to be set first"
+            );
+        }
+        self.mode = SeccompMode::Filter;
+        self.filters.push(Arc::new(filter));
+        Ok(())
+    }
+
+    /// Evaluates all installed filters, in install order, and returns the
+    /// highest-priority (most restrictive) action.
+    pub fn evaluate(&self, data: &SeccompData) -> SeccompAction {
+        self.filters
+            .iter()
+            .map(|filter| filter.evaluate(data))
+            .max_by_key(|action| action.priority())
+            .unwrap_or(SeccompAction::Allow)
+    }
+}
+
+impl Default for SeccompState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process {
+    pub fn seccomp(&self) -> &Mutex<SeccompState> {
+        &self.seccomp
+    }
+
+    /// Runs the installed seccomp filters (if any) over a syscall about to
+    /// be executed, and returns `Ok(())` if the syscall may proceed.
+    ///
+    /// The syscall-entry trap must call this before dispatch, for every
+    /// syscall. That dispatch loop lives in `crate::syscall` (outside this
+    /// slice of the tree, which only covers `jinux-std/src/process`) and
+    /// does not call this yet — this is implemented and ready, but has no
+    /// caller until that file adds one.
+    pub fn seccomp_check_syscall(&self, data: &SeccompData) -> Result<()> {
+        let state = self.seccomp.lock();
+        match state.mode() {
+            SeccompMode::Disabled => Ok(()),
+            SeccompMode::Strict => self.seccomp_check_strict(data),
+            SeccompMode::Filter => self.seccomp_check_filter(&state, data),
+        }
+    }
+
+    /// SECCOMP_MODE_STRICT: only `read`, `write`, `_exit`/`exit_group` and
+    /// `rt_sigreturn` are permitted; anything else kills the process with
+    /// `SIGKILL`, same as Linux.
+    fn seccomp_check_strict(&self, data: &SeccompData) -> Result<()> {
+        const SYS_READ: i32 = 0;
+        const SYS_WRITE: i32 = 1;
+        const SYS_RT_SIGRETURN: i32 = 15;
+        const SYS_EXIT: i32 = 60;
+        const SYS_EXIT_GROUP: i32 = 231;
+
+        if matches!(
+            data.nr,
+            SYS_READ | SYS_WRITE | SYS_RT_SIGRETURN | SYS_EXIT | SYS_EXIT_GROUP
+        ) {
+            return Ok(());
+        }
+        self.exit_group(128 + SIGKILL.as_u8() as i32, Some((SIGKILL, false)));
+        return_errno_with_message!(Errno::EACCES, "killed by seccomp strict mode")
+    }
+
+    fn seccomp_check_filter(&self, state: &SeccompState, data: &SeccompData) -> Result<()> {
+        match state.evaluate(data) {
+            SeccompAction::Allow => Ok(()),
+            SeccompAction::Errno(errno) => {
+                return_errno_with_message!(
+                    Errno::try_from(errno).unwrap_or(Errno::EACCES),
+                    "blocked by seccomp filter"
+                )
+            }
+            SeccompAction::Trap => {
+                self.enqueue_signal(Box::new(KernelSignal::new(SIGSYS)));
+                return_errno_with_message!(Errno::EACCES, "blocked by seccomp filter (SIGSYS)")
+            }
+            SeccompAction::KillThread => {
+                self.exit_group(128 + SIGSYS.as_u8() as i32, Some((SIGSYS, false)));
+                return_errno_with_message!(Errno::EACCES, "killed by seccomp filter")
+            }
+            SeccompAction::KillProcess => {
+                self.exit_group(128 + SIGSYS.as_u8() as i32, Some((SIGSYS, false)));
+                return_errno_with_message!(Errno::EACCES, "killed by seccomp filter")
+            }
+        }
+    }
+}
+
+/// Handles the `seccomp` syscall.
+pub fn sys_seccomp(operation: i32, _flags: u32, args: Vaddr) -> Result<SyscallReturn> {
+    let current = current!();
+    // Installing or tightening a seccomp filter is itself a "proc"
+    // operation on the calling process; a process that pledged away
+    // `proc` can no longer touch its own sandboxing state either.
+    current.check_promise(super::veil::PledgeSet::PROC)?;
+    match operation {
+        SECCOMP_SET_MODE_STRICT => {
+            current.seccomp().lock().mode = SeccompMode::Strict;
+            Ok(SyscallReturn::Return(0))
+        }
+        SECCOMP_SET_MODE_FILTER => {
+            let filter = read_seccomp_filter_from_user(args)?;
+            current.seccomp().lock().add_filter(filter)?;
+            Ok(SyscallReturn::Return(0))
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "unsupported seccomp operation"),
+    }
+}
+
+/// Handles the legacy `prctl(PR_SET_SECCOMP, ...)` and
+/// `prctl(PR_SET_NO_NEW_PRIVS, ...)` requests.
+///
+/// `arg2` is the seccomp mode for `PR_SET_SECCOMP`; `arg3` is only
+/// meaningful for that request, and carries the `struct sock_fprog *`
+/// (real `prctl(PR_SET_SECCOMP, mode, filter_ptr)` takes the filter as a
+/// separate third argument, not packed into `arg2`).
+pub fn sys_prctl_seccomp(option: i32, arg2: u64, arg3: u64) -> Result<SyscallReturn> {
+    let current = current!();
+    match option {
+        PR_SET_NO_NEW_PRIVS => {
+            current.seccomp().lock().set_no_new_privs();
+            Ok(SyscallReturn::Return(0))
+        }
+        PR_GET_NO_NEW_PRIVS => {
+            Ok(SyscallReturn::Return(current.seccomp().lock().no_new_privs() as _))
+        }
+        PR_SET_SECCOMP => {
+            current.check_promise(super::veil::PledgeSet::PROC)?;
+            if arg2 as i32 != SECCOMP_MODE_FILTER {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "PR_SET_SECCOMP only supports SECCOMP_MODE_FILTER"
+                );
+            }
+            let filter = read_seccomp_filter_from_user(arg3 as Vaddr)?;
+            current.seccomp().lock().add_filter(filter)?;
+            Ok(SyscallReturn::Return(0))
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "unsupported prctl option"),
+    }
+}
+
+/// Size in bytes of a `struct sock_fprog { unsigned short len; struct
+/// sock_filter *filter; }` on a 64-bit target: a `u16` len, 6 bytes of
+/// padding, then a pointer.
+const SOCK_FPROG_SIZE: usize = 16;
+/// Size in bytes of a single `struct sock_filter { __u16 code; __u8 jt, jf;
+/// __u32 k; }`.
+const SOCK_FILTER_SIZE: usize = 8;
+/// The maximum BPF program length `seccomp`/`prctl` accept, matching Linux's
+/// `BPF_MAXINSNS`.
+const BPF_MAXINSNS: usize = 4096;
+
+fn read_seccomp_filter_from_user(user_args: Vaddr) -> Result<SeccompFilter> {
+    let vmar = current!().root_vmar().clone();
+
+    let mut fprog_buf = [0u8; SOCK_FPROG_SIZE];
+    vmar.read_bytes(user_args, &mut fprog_buf)?;
+    let len = u16::from_ne_bytes([fprog_buf[0], fprog_buf[1]]) as usize;
+    let filter_ptr = usize::from_ne_bytes(fprog_buf[8..16].try_into().unwrap());
+
+    if len == 0 || len > BPF_MAXINSNS {
+        return_errno_with_message!(Errno::EINVAL, "invalid seccomp BPF program length");
+    }
+
+    let mut program = Vec::with_capacity(len);
+    let mut insn_buf = [0u8; SOCK_FILTER_SIZE];
+    for i in 0..len {
+        vmar.read_bytes(filter_ptr + i * SOCK_FILTER_SIZE, &mut insn_buf)?;
+        program.push(BpfInstruction {
+            code: u16::from_ne_bytes([insn_buf[0], insn_buf[1]]),
+            jt: insn_buf[2],
+            jf: insn_buf[3],
+            k: u32::from_ne_bytes(insn_buf[4..8].try_into().unwrap()),
+        });
+    }
+
+    Ok(SeccompFilter::new(program))
+}
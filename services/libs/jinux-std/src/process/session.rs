@@ -0,0 +1,106 @@
+use super::process_group::ProcessGroup;
+use super::{Pgid, Pid, Process};
+use crate::device::tty::Tty;
+use crate::prelude::*;
+use crate::syscall::SyscallReturn;
+
+/// A session is a collection of process groups, analogous to how a process
+/// group is a collection of processes. Sessions are used for job control:
+/// the session leader may own a controlling terminal, and the terminal in
+/// turn designates one process group within the session as the foreground
+/// group.
+pub struct Session {
+    sid: Pid,
+    /// The process that created the session (and whose pid equals `sid`).
+    leader: Mutex<Weak<Process>>,
+    /// All process groups that currently belong to this session.
+    process_groups: Mutex<BTreeMap<Pgid, Arc<ProcessGroup>>>,
+    /// The controlling terminal of the session, if any.
+    controlling_terminal: Mutex<Option<&'static Tty>>,
+}
+
+impl Session {
+    /// Creates a new session whose leader is `leader`.
+    pub fn new(leader: &Arc<Process>) -> Arc<Self> {
+        Arc::new(Self {
+            sid: leader.pid(),
+            leader: Mutex::new(Arc::downgrade(leader)),
+            process_groups: Mutex::new(BTreeMap::new()),
+            controlling_terminal: Mutex::new(None),
+        })
+    }
+
+    /// Returns the session id, which is the pid of the session leader.
+    pub fn sid(&self) -> Pid {
+        self.sid
+    }
+
+    pub fn leader(&self) -> Option<Arc<Process>> {
+        self.leader.lock().upgrade()
+    }
+
+    /// Adds a process group to this session.
+    pub fn add_process_group(&self, process_group: Arc<ProcessGroup>) {
+        self.process_groups
+            .lock()
+            .insert(process_group.pgid(), process_group);
+    }
+
+    /// Removes a process group from this session.
+    pub fn remove_process_group(&self, pgid: Pgid) {
+        self.process_groups.lock().remove(&pgid);
+    }
+
+    /// Makes `tty` the controlling terminal of the session and sets `pgid`
+    /// as its foreground process group.
+    pub fn set_controlling_terminal(&self, tty: &'static Tty, pgid: Pgid) {
+        tty.set_fg(pgid);
+        *self.controlling_terminal.lock() = Some(tty);
+    }
+
+    /// Relinquishes the controlling terminal of the session, if any.
+    pub fn release_controlling_terminal(&self) {
+        if let Some(tty) = self.controlling_terminal.lock().take() {
+            tty.set_fg(0);
+        }
+    }
+
+    /// Returns the foreground process group of the controlling terminal,
+    /// if the session has one.
+    pub fn foreground_process_group(&self) -> Option<Arc<ProcessGroup>> {
+        let terminal = self.controlling_terminal.lock();
+        let tty = (*terminal)?;
+        self.process_groups.lock().get(&tty.fg_pgid()).cloned()
+    }
+}
+
+/// Handles the `setsid` syscall.
+///
+/// Creates a new session and a new process group, both led by the calling
+/// process, and detaches the caller from any controlling terminal. Fails
+/// with `EPERM` if the calling process is already a process group leader.
+pub fn sys_setsid() -> Result<SyscallReturn> {
+    let current = current!();
+
+    if current.pgid() == current.pid() {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the calling process is already a process group leader"
+        );
+    }
+
+    current.create_and_set_session_and_group();
+    Ok(SyscallReturn::Return(current.sid() as _))
+}
+
+/// Handles the `getsid` syscall.
+pub fn sys_getsid(pid: Pid) -> Result<SyscallReturn> {
+    let current = current!();
+    let process = if pid == 0 {
+        current
+    } else {
+        super::process_table::pid_to_process(pid)
+            .ok_or_else(|| Error::with_message(Errno::ESRCH, "the target process does not exist"))?
+    };
+    Ok(SyscallReturn::Return(process.sid() as _))
+}
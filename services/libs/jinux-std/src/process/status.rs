@@ -0,0 +1,77 @@
+use super::signal::constants::SigNum;
+
+/// The scheduling state of a process, as observed by `wait`/`waitpid`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStatus {
+    /// The process is running (or runnable) normally.
+    Runnable,
+    /// The process is job-control-stopped by `signal` (e.g. `SIGSTOP`), not
+    /// yet reported to a `WUNTRACED` waiter.
+    Stopped(SigNum),
+    /// Already reported to a `WUNTRACED` waiter; the process remains
+    /// stopped until `SIGCONT` is delivered.
+    StoppedReported(SigNum),
+    /// The process was resumed by `SIGCONT`, not yet reported to a
+    /// `WCONTINUED` waiter.
+    Continued,
+    /// The process has exited and is awaiting reaping by its parent.
+    Zombie,
+}
+
+impl ProcessStatus {
+    pub fn is_zombie(&self) -> bool {
+        matches!(self, Self::Zombie)
+    }
+
+    pub fn set_zombie(&mut self) {
+        *self = Self::Zombie;
+    }
+
+    /// Whether this process is stopped and that stop has not yet been
+    /// reported to a `WUNTRACED` waiter.
+    pub fn is_stopped(&self) -> bool {
+        matches!(self, Self::Stopped(_))
+    }
+
+    /// Reports (at most once per stop) the signal this process was stopped
+    /// by, mirroring `take_continued`'s one-shot semantics.
+    pub fn take_stop_signal(&mut self) -> Option<SigNum> {
+        if let Self::Stopped(signal) = *self {
+            *self = Self::StoppedReported(signal);
+            Some(signal)
+        } else {
+            None
+        }
+    }
+
+    /// Reports (at most once) that this process was resumed by `SIGCONT`.
+    pub fn take_continued(&mut self) -> bool {
+        if matches!(self, Self::Continued) {
+            *self = Self::Runnable;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Enters the stopped state for job control (e.g. on `SIGSTOP`). A
+    /// zombie process cannot be stopped.
+    pub fn stop(&mut self, signal: SigNum) {
+        if !self.is_zombie() {
+            *self = Self::Stopped(signal);
+        }
+    }
+
+    /// Resumes a stopped process on `SIGCONT`, moving it to `Continued` so a
+    /// `WCONTINUED` waiter can observe the transition. Returns `false` (and
+    /// leaves the status unchanged) if the process was not stopped.
+    pub fn resume_from_stop(&mut self) -> bool {
+        match self {
+            Self::Stopped(_) | Self::StoppedReported(_) => {
+                *self = Self::Continued;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::prelude::*;
+use crate::syscall::SyscallReturn;
+
+use super::Process;
+
+bitflags::bitflags! {
+    /// The access modes an unveil rule can grant for a path prefix.
+    pub struct VeilAccess: u32 {
+        const READ = 1 << 0;
+        const WRITE = 1 << 1;
+        const EXECUTE = 1 << 2;
+        const CREATE = 1 << 3;
+    }
+}
+
+bitflags::bitflags! {
+    /// The syscall classes a pledged process is allowed to use.
+    pub struct PledgeSet: u32 {
+        const STDIO = 1 << 0;
+        const RPATH = 1 << 1;
+        const WPATH = 1 << 2;
+        const CPATH = 1 << 3;
+        const EXEC = 1 << 4;
+        const PROC = 1 << 5;
+        const NET = 1 << 6;
+    }
+}
+
+/// Returns whether `prefix` is a path-component prefix of `path`: either an
+/// exact match, or a match followed immediately by `/`. A plain
+/// `str::starts_with` would let unveiling `/etc` also grant `/etc-backup` or
+/// `/etcetera`, which defeats the whole point of a confinement mechanism.
+fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.strip_suffix('/').unwrap_or(prefix);
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// The lifecycle of a process's unveil set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VeilLifecycle {
+    /// `unveil` has never been called: all paths are accessible.
+    None,
+    /// `unveil` has been called at least once and the set may still grow.
+    Dropped,
+    /// `unveil(NULL, NULL)` was called: the set is final and immutable.
+    Locked,
+    /// The set was inherited from the parent across `execve` and is final;
+    /// the new program's own `unveil`/`pledge` calls are silently ignored.
+    LockedInherited,
+}
+
+/// A process's veil (unveil) sandbox state. The pledge bitset itself lives
+/// in `Process::promises`; this only tracks the `execpromises` that must
+/// survive an `execve`.
+#[derive(Debug, Clone)]
+pub struct VeilState {
+    lifecycle: VeilLifecycle,
+    /// Path prefix -> permitted access bits, checked longest-prefix-first.
+    unveils: BTreeMap<String, VeilAccess>,
+    execpromises: PledgeSet,
+}
+
+impl VeilState {
+    pub fn new() -> Self {
+        Self {
+            lifecycle: VeilLifecycle::None,
+            unveils: BTreeMap::new(),
+            execpromises: PledgeSet::all(),
+        }
+    }
+
+    pub fn lifecycle(&self) -> VeilLifecycle {
+        self.lifecycle
+    }
+
+    pub fn execpromises(&self) -> PledgeSet {
+        self.execpromises
+    }
+
+    /// Adds an unveil rule for `path`, replacing any existing rule for the
+    /// same path. Fails with `EPERM` when the veil is locked; silently
+    /// ignored when the veil was inherited across exec.
+    pub fn unveil(&mut self, path: &str, access: VeilAccess) -> Result<()> {
+        match self.lifecycle {
+            VeilLifecycle::Locked => {
+                return_errno_with_message!(Errno::EPERM, "the veil is locked")
+            }
+            VeilLifecycle::LockedInherited => {
+                // Silently ignored: the inherited restrictions still apply.
+                Ok(())
+            }
+            VeilLifecycle::None | VeilLifecycle::Dropped => {
+                self.lifecycle = VeilLifecycle::Dropped;
+                self.unveils.insert(path.to_string(), access);
+                Ok(())
+            }
+        }
+    }
+
+    /// Locks the veil, preventing any further `unveil` calls.
+    pub fn lock(&mut self) {
+        if self.lifecycle != VeilLifecycle::LockedInherited {
+            self.lifecycle = VeilLifecycle::Locked;
+        }
+    }
+
+    /// Records the `execpromises` to carry forward into the next `execve`.
+    /// Ignored when the veil was inherited across exec. Like `promises`,
+    /// `execpromises` may only be narrowed, never widened: a process must
+    /// not be able to `pledge` its way back to a wider exec-time sandbox
+    /// than it already committed to.
+    pub fn set_execpromises(&mut self, execpromises: PledgeSet) -> Result<()> {
+        if self.lifecycle == VeilLifecycle::LockedInherited {
+            return Ok(());
+        }
+        if !self.execpromises.contains(execpromises) {
+            return_errno_with_message!(
+                Errno::EPERM,
+                "cannot pledge an execpromise that was not already held"
+            );
+        }
+        self.execpromises = execpromises;
+        Ok(())
+    }
+
+    /// Checks whether `path` is permitted for the given access. Allowed
+    /// unconditionally if the veil has never been dropped.
+    ///
+    /// Every `FsResolver` lookup that resolves a path on behalf of a
+    /// process must call this (via `Process::check_path_access`) before
+    /// using the result. `FsResolver`'s own implementation lives outside
+    /// `jinux-std/src/process` (in `crate::fs::fs_resolver`, not part of
+    /// this slice of the tree) and still needs to be updated to do so.
+    pub fn check_path(&self, path: &str, access: VeilAccess) -> Result<()> {
+        if self.lifecycle == VeilLifecycle::None {
+            return Ok(());
+        }
+        let Some((_, allowed)) = self
+            .unveils
+            .iter()
+            .filter(|(prefix, _)| path_has_prefix(path, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+        else {
+            return_errno_with_message!(Errno::ENOENT, "path is not unveiled");
+        };
+        if !allowed.contains(access) {
+            return_errno_with_message!(Errno::EACCES, "access is not permitted by unveil");
+        }
+        Ok(())
+    }
+
+    /// Produces the veil state a child process should have right after
+    /// `execve`: the unveil set carries over unchanged and the lifecycle
+    /// becomes `LockedInherited` so the new program cannot widen its own
+    /// sandbox, even though the inherited restrictions still apply. If
+    /// `unveil` was never called, the lifecycle stays `None` (no path
+    /// restriction) and only `execpromises` carries over.
+    ///
+    /// `execpromises` carries over regardless of `lifecycle`: `pledge`'s
+    /// `execpromises` and `unveil`'s lifecycle are independent, so a process
+    /// that only ever called `pledge` (never `unveil`) must still hand its
+    /// pledged `execpromises` to the next program, not the unrestricted
+    /// default `PledgeSet::all()`.
+    pub fn inherit_across_exec(&self) -> Self {
+        if self.lifecycle == VeilLifecycle::None {
+            return Self {
+                execpromises: self.execpromises,
+                ..Self::new()
+            };
+        }
+        Self {
+            lifecycle: VeilLifecycle::LockedInherited,
+            unveils: self.unveils.clone(),
+            execpromises: self.execpromises,
+        }
+    }
+}
+
+impl Default for VeilState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process {
+    pub fn veil(&self) -> &RwLock<VeilState> {
+        &self.veil
+    }
+
+    pub fn promises(&self) -> &RwLock<PledgeSet> {
+        &self.promises
+    }
+
+    /// Checks whether `promise` is currently pledged.
+    pub fn check_promise(&self, promise: PledgeSet) -> Result<()> {
+        if !self.promises.read().contains(promise) {
+            return_errno_with_message!(Errno::EPERM, "operation is not permitted by pledge");
+        }
+        Ok(())
+    }
+
+    /// Checks whether `path` is permitted for `access` under this process's
+    /// unveil sandbox. The single call-site every `FsResolver` path lookup
+    /// must make before acting on a resolved path.
+    pub fn check_path_access(&self, path: &str, access: VeilAccess) -> Result<()> {
+        self.veil.read().check_path(path, access)
+    }
+
+    /// Carries the veil/pledge sandbox forward across `execve`.
+    ///
+    /// This tree has no `execve` syscall yet (`program_loader` is declared
+    /// but not implemented in this slice), so nothing calls this yet either;
+    /// wire it in from `execve`'s implementation, before the new program
+    /// image is run, once that exists.
+    pub fn inherit_veil_across_exec(&self) {
+        let inherited = self.veil.read().inherit_across_exec();
+        *self.promises.write() = inherited.execpromises();
+        *self.veil.write() = inherited;
+    }
+}
+
+/// Handles the `unveil` syscall.
+pub fn sys_unveil(path: CString, permissions: CString) -> Result<SyscallReturn> {
+    let current = current!();
+    let path = path.to_string_lossy();
+    let permissions = permissions.to_string_lossy();
+
+    // A NULL path (represented here as an empty string) locks the veil.
+    if path.is_empty() {
+        current.veil().write().lock();
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let mut access = VeilAccess::empty();
+    for c in permissions.chars() {
+        match c {
+            'r' => access |= VeilAccess::READ,
+            'w' => access |= VeilAccess::WRITE,
+            'x' => access |= VeilAccess::EXECUTE,
+            'c' => access |= VeilAccess::CREATE,
+            _ => return_errno_with_message!(Errno::EINVAL, "unknown unveil permission character"),
+        }
+    }
+    current.veil().write().unveil(&path, access)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Handles the `pledge` syscall. A process may only narrow its promises,
+/// never widen them.
+pub fn sys_pledge(promises: u32, execpromises: u32) -> Result<SyscallReturn> {
+    let current = current!();
+    let promises = PledgeSet::from_bits_truncate(promises);
+    let execpromises = PledgeSet::from_bits_truncate(execpromises);
+
+    // Silently ignored, like `unveil`, once the sandbox was inherited
+    // across exec.
+    if current.veil().read().lifecycle() == VeilLifecycle::LockedInherited {
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    if !current.promises().read().contains(promises) {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "cannot pledge a promise that was not already held"
+        );
+    }
+
+    current.veil().write().set_execpromises(execpromises)?;
+    *current.promises().write() = promises;
+    Ok(SyscallReturn::Return(0))
+}
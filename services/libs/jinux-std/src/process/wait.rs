@@ -0,0 +1,164 @@
+use super::exit_status::ExitStatus;
+use super::{Pid, Process};
+use crate::prelude::*;
+use crate::syscall::SyscallReturn;
+
+bitflags::bitflags! {
+    /// Options accepted by the `waitpid`/`wait4` syscalls, controlling which
+    /// child state transitions are reported and whether the call blocks.
+    pub struct WaitOptions: u32 {
+        /// Return immediately if no matching child has changed state yet.
+        const WNOHANG = 0x0000_0001;
+        /// Also report children stopped by a signal, without reaping them.
+        const WUNTRACED = 0x0000_0002;
+        /// Also report children that were resumed by `SIGCONT`.
+        const WCONTINUED = 0x0000_0008;
+    }
+}
+
+impl Process {
+    /// Waits for a child matching `pid` (or any child, if `pid` is `None`)
+    /// to change state, honoring `options`.
+    ///
+    /// Returns `Ok(None)` only when `WNOHANG` was requested and no matching
+    /// child has changed state. Reaps the child (removing it from the
+    /// process table) when it reports a normal zombie exit; stopped and
+    /// continued reports leave the child alive, per `WUNTRACED`/`WCONTINUED`
+    /// semantics.
+    pub fn wait_for_child(
+        self: &Arc<Self>,
+        pid: Option<Pid>,
+        options: WaitOptions,
+    ) -> Result<Option<(Pid, ExitStatus)>> {
+        loop {
+            if let Some(result) = self.poll_child(pid, options)? {
+                return Ok(Some(result));
+            }
+
+            if options.contains(WaitOptions::WNOHANG) {
+                return Ok(None);
+            }
+
+            return self
+                .waiting_children()
+                .wait_until(|| self.poll_child(pid, options).transpose())
+                .map(Some);
+        }
+    }
+
+    fn poll_child(
+        self: &Arc<Self>,
+        pid: Option<Pid>,
+        options: WaitOptions,
+    ) -> Result<Option<(Pid, ExitStatus)>> {
+        let matches = |child: &Arc<Process>| pid.map_or(true, |pid| child.pid() == pid);
+
+        let zombie_pid = {
+            let children = self.children().lock();
+            let has_matching_child = children.values().any(|child| matches(child));
+            let has_matching_tracee = self
+                .tracees()
+                .lock()
+                .iter()
+                .any(|tracee| tracee.upgrade().is_some_and(|tracee| matches(&tracee)));
+            if !has_matching_child && !has_matching_tracee {
+                return_errno_with_message!(Errno::ECHILD, "no matching child process");
+            }
+            children
+                .iter()
+                .find(|(_, child)| matches(child) && child.status().lock().is_zombie())
+                .map(|(pid, _)| *pid)
+        };
+        if let Some(zombie_pid) = zombie_pid {
+            // Another waiter may have reaped this same zombie between the
+            // lookup above and this call (the children lock is released in
+            // between); if so, just fall through as if no zombie had
+            // been found this round instead of treating it as an error.
+            if let Some(exit_status) = self.reap_zombie_child(zombie_pid) {
+                return Ok(Some((zombie_pid, exit_status)));
+            }
+        }
+
+        if options.contains(WaitOptions::WUNTRACED) {
+            if let Some(status) = self.take_stopped_child(pid, &matches) {
+                return Ok(Some(status));
+            }
+        }
+
+        if options.contains(WaitOptions::WCONTINUED) {
+            if let Some(status) = self.take_continued_child(pid, &matches) {
+                return Ok(Some(status));
+            }
+        }
+
+        // A tracee stop is reportable regardless of `WUNTRACED`: a tracer
+        // always waits like a debugger, whether or not the tracee is also
+        // one of its children (PTRACE_ATTACH/PTRACE_SEIZE need not target a
+        // child at all).
+        if let Some(status) = self.take_tracee_stop(pid) {
+            return Ok(Some(status));
+        }
+
+        Ok(None)
+    }
+
+    /// Reports a child's stop at most once per stop, mirroring
+    /// `take_continued_child`'s one-shot `take_continued()`: otherwise a
+    /// blocking `wait4(..., WUNTRACED)` would observe the same still-stopped
+    /// child forever instead of blocking until its next state transition.
+    fn take_stopped_child(
+        self: &Arc<Self>,
+        _pid: Option<Pid>,
+        matches: &dyn Fn(&Arc<Process>) -> bool,
+    ) -> Option<(Pid, ExitStatus)> {
+        let children = self.children().lock();
+        children
+            .iter()
+            .find(|(_, child)| matches(child) && child.status().lock().is_stopped())
+            .and_then(|(&pid, child)| {
+                child
+                    .status()
+                    .lock()
+                    .take_stop_signal()
+                    .map(|signal| (pid, ExitStatus::Stopped(signal)))
+            })
+    }
+
+    fn take_continued_child(
+        self: &Arc<Self>,
+        _pid: Option<Pid>,
+        matches: &dyn Fn(&Arc<Process>) -> bool,
+    ) -> Option<(Pid, ExitStatus)> {
+        let children = self.children().lock();
+        children
+            .iter()
+            .find(|(_, child)| matches(child) && child.status().lock().take_continued())
+            .map(|(&pid, _)| (pid, ExitStatus::Continued))
+    }
+}
+
+/// Handles the `wait4`/`waitpid` syscalls.
+///
+/// `pid > 0` waits for that specific child; `pid <= 0` (any negative value
+/// or `-1`) waits for any child, matching the subset of `wait4`'s pid
+/// semantics this tree implements (process-group-scoped waits, `pid == 0`,
+/// are not supported yet). `wstatus_addr` receives the packed status Linux
+/// userspace expects (see `ExitStatus::as_wstatus`); a null address skips
+/// the write, as real `wait4` allows.
+pub fn sys_wait4(pid: i32, wstatus_addr: Vaddr, options: u32, _rusage_addr: Vaddr) -> Result<SyscallReturn> {
+    let current = current!();
+    let pid = if pid > 0 { Some(pid as Pid) } else { None };
+    let options = WaitOptions::from_bits(options)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid wait options"))?;
+
+    let Some((reaped_pid, exit_status)) = current.wait_for_child(pid, options)? else {
+        return Ok(SyscallReturn::Return(0));
+    };
+
+    if wstatus_addr != 0 {
+        current
+            .root_vmar()
+            .write_bytes(wstatus_addr, &exit_status.as_wstatus().to_ne_bytes())?;
+    }
+    Ok(SyscallReturn::Return(reaped_pid as _))
+}